@@ -8,7 +8,8 @@ use std::{
 
 use hir::{
     BasicBlock, BlockId, BlockKind, Environment, GotoKind, Identifier, IdentifierData, Instruction,
-    InstructionIdGenerator, InstructionValue, Place, Terminal, TerminalValue, Type, HIR,
+    InstructionIdGenerator, InstructionValue, Place, Primitive, SwitchTerminal, Terminal,
+    TerminalValue, Type, HIR,
 };
 use indexmap::IndexMap;
 
@@ -190,6 +191,29 @@ impl<'a> Builder<'a> {
             identifier
         }
     }
+
+    /// Terminates the current block with a multi-way `SwitchTerminal`,
+    /// matching `discriminant` against each of `cases`' constant values and
+    /// falling through to `default` otherwise. Used when lowering a
+    /// `switch` statement whose cases have already been lowered to their
+    /// own blocks.
+    pub(crate) fn terminate_switch(
+        &mut self,
+        discriminant: Place<'a>,
+        cases: std::vec::Vec<(Primitive, BlockId)>,
+        default: BlockId,
+        fallthrough: Option<BlockId>,
+    ) {
+        self.terminate(
+            TerminalValue::SwitchTerminal(SwitchTerminal {
+                discriminant,
+                cases,
+                default,
+                fallthrough,
+            }),
+            BlockKind::Block,
+        );
+    }
 }
 
 pub(crate) enum Binding<'a> {
@@ -200,7 +224,7 @@ pub(crate) enum Binding<'a> {
 
 /// Modifies the HIR to put the blocks in reverse postorder, with predecessors before
 /// successors (except for the case of loops)
-fn reverse_postorder_blocks<'a>(hir: &mut HIR<'a>) {
+pub(crate) fn reverse_postorder_blocks<'a>(hir: &mut HIR<'a>) {
     let mut visited = HashSet::<BlockId>::with_capacity(hir.blocks.len());
     let mut postorder = std::vec::Vec::<BlockId>::with_capacity(hir.blocks.len());
     fn visit<'a>(
@@ -229,6 +253,12 @@ fn reverse_postorder_blocks<'a>(hir: &mut HIR<'a>) {
             TerminalValue::GotoTerminal(terminal) => {
                 visit(terminal.block, hir, visited, postorder);
             }
+            TerminalValue::SwitchTerminal(terminal) => {
+                visit(terminal.default, hir, visited, postorder);
+                for (_, case) in terminal.cases.iter().rev() {
+                    visit(*case, hir, visited, postorder);
+                }
+            }
             TerminalValue::ReturnTerminal(..) => { /* no-op */ }
         }
         postorder.push(block_id);
@@ -302,7 +332,7 @@ fn remove_unreachable_do_while_statements<'a>(hir: &mut HIR<'a>) {
 
 /// Updates the instruction ids for all instructions and blocks
 /// Relies on the blocks being in reverse postorder to ensure that id ordering is correct
-fn mark_instruction_ids<'a>(hir: &mut HIR<'a>) -> Result<(), Diagnostic> {
+pub(crate) fn mark_instruction_ids<'a>(hir: &mut HIR<'a>) -> Result<(), Diagnostic> {
     let mut id_gen = InstructionIdGenerator::new();
     let mut visited = HashSet::<(usize, usize)>::new();
     for (block_ix, block) in hir.blocks.values_mut().enumerate() {
@@ -318,7 +348,7 @@ fn mark_instruction_ids<'a>(hir: &mut HIR<'a>) -> Result<(), Diagnostic> {
 }
 
 /// Updates the predecessors of each block
-fn mark_predecessors<'a>(hir: &mut HIR<'a>) {
+pub(crate) fn mark_predecessors<'a>(hir: &mut HIR<'a>) {
     for block in hir.blocks.values_mut() {
         block.predecessors.clear();
     }
@@ -354,4 +384,4 @@ where
     Ok(())
 }
 
-type Diagnostic = ();
+pub(crate) type Diagnostic = ();
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, VecDeque};
+
+use hir::{
+    BinaryOperator, BlockId, GotoKind, GotoTerminal, Identifier, IdentifierId, InstructionValue,
+    Place, Primitive, TerminalValue, HIR,
+};
+
+use crate::builder::{mark_instruction_ids, mark_predecessors, reverse_postorder_blocks};
+
+/// The sparse-conditional-constant-propagation lattice for a single
+/// `Identifier`. Mirrors rustc's `dataflow_const_prop`: `Top` means "not a
+/// single known constant" (eg different constants along different paths),
+/// `Const` means "always this literal value along every path
+/// reached so far", and `Bottom` means "unreachable", ie no path to this
+/// point has been proven reachable yet.
+#[derive(Debug, Clone, PartialEq)]
+enum LatticeValue {
+    Top,
+    Const(Primitive),
+    Bottom,
+}
+
+impl LatticeValue {
+    /// Meets (⊓) two lattice values at a control-flow join point.
+    fn meet(&self, other: &LatticeValue) -> LatticeValue {
+        match (self, other) {
+            (LatticeValue::Bottom, other) => other.clone(),
+            (this, LatticeValue::Bottom) => this.clone(),
+            (LatticeValue::Const(a), LatticeValue::Const(b)) if a == b => {
+                LatticeValue::Const(a.clone())
+            }
+            _ => LatticeValue::Top,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lattice {
+    values: HashMap<IdentifierId, LatticeValue>,
+}
+
+impl Lattice {
+    fn get(&self, identifier: &Identifier) -> LatticeValue {
+        self.values
+            .get(&identifier.id)
+            .cloned()
+            .unwrap_or(LatticeValue::Bottom)
+    }
+
+    fn set(&mut self, identifier: &Identifier, value: LatticeValue) {
+        self.values.insert(identifier.id, value);
+    }
+}
+
+/// Folds constants through the HIR produced by `Builder::build`, modeled
+/// on rustc's `const_prop`/`dataflow_const_prop`. Runs a worklist fixpoint
+/// seeded with the entry block: blocks are first visited breadth-first as
+/// they become reachable, and all reachable blocks are revisited in the
+/// builder's reverse-postorder whenever a lattice value changes. It
+/// specially handles `IfTerminal`s
+/// with a known condition so that only the reachable branch contributes
+/// to the lattice (this is the "conditional" part of sparse conditional
+/// constant propagation; it prevents unreachable branches from polluting
+/// the analysis).
+pub fn constant_propagation(hir: &mut HIR) {
+    let mut lattice = Lattice::default();
+    let mut reachable: std::collections::HashSet<BlockId> = Default::default();
+    let mut worklist: VecDeque<BlockId> = VecDeque::new();
+    worklist.push_back(hir.entry);
+    reachable.insert(hir.entry);
+
+    while let Some(block_id) = worklist.pop_front() {
+        let block = hir.block(block_id);
+
+        // Evaluate the block's instructions against the current lattice,
+        // updating each lvalue's entry in place.
+        let mut updates = Vec::new();
+        for instruction in &block.instructions {
+            let value = eval_instruction(&instruction.value, &lattice);
+            updates.push((instruction.lvalue.identifier.clone(), value));
+        }
+        let mut changed = false;
+        for (identifier, value) in updates {
+            // Join with whatever the lattice already held for this
+            // identifier rather than overwriting outright: if a prior
+            // iteration reached this same assignment along a different
+            // incoming path with a different constant, the correct lattice
+            // state is `Top` (unknown), not whichever path happened to be
+            // processed last.
+            let previous = lattice.get(&identifier);
+            let joined = previous.meet(&value);
+            changed |= joined != previous;
+            lattice.set(&identifier, joined);
+        }
+
+        // Determine which successors are provably reachable given the
+        // current lattice state of the terminal's operands.
+        let successors = match &block.terminal.value {
+            TerminalValue::IfTerminal(terminal) => {
+                match condition_const(&terminal.test, &lattice) {
+                    Some(Primitive::Boolean(true)) => vec![terminal.consequent],
+                    Some(Primitive::Boolean(false)) => vec![terminal.alternate],
+                    _ => vec![terminal.consequent, terminal.alternate],
+                }
+            }
+            other => other.successors(),
+        };
+
+        for successor in successors {
+            if reachable.insert(successor) {
+                worklist.push_back(successor);
+            }
+        }
+
+        if changed {
+            // A lattice value moved and any reachable block may read it:
+            // revisit them all until nothing changes.
+            let revisit: Vec<BlockId> = hir
+                .blocks
+                .keys()
+                .filter(|id| reachable.contains(*id) && !worklist.contains(*id))
+                .cloned()
+                .collect();
+            worklist.extend(revisit);
+        }
+    }
+
+    rewrite_constants(hir, &lattice);
+
+    reverse_postorder_blocks(hir);
+    let _ = mark_instruction_ids(hir);
+    mark_predecessors(hir);
+}
+
+/// Evaluates a single instruction against the current lattice state,
+/// returning `Const` if every operand is a known constant and the
+/// operation is pure, `Bottom` if any operand is `Bottom`, or `Top`
+/// for anything not (yet) modeled.
+fn eval_instruction(value: &InstructionValue, lattice: &Lattice) -> LatticeValue {
+    match value {
+        InstructionValue::Primitive(primitive) => LatticeValue::Const(primitive.clone()),
+        InstructionValue::LoadLocal(load) => lattice.get(&load.place.identifier),
+        InstructionValue::BinaryExpression(binary) => {
+            match (
+                lattice.get(&binary.left.identifier),
+                lattice.get(&binary.right.identifier),
+            ) {
+                (LatticeValue::Const(left), LatticeValue::Const(right)) => {
+                    match eval_binary_operator(binary.operator, &left, &right) {
+                        Some(result) => LatticeValue::Const(result),
+                        None => LatticeValue::Top,
+                    }
+                }
+                (LatticeValue::Bottom, _) | (_, LatticeValue::Bottom) => LatticeValue::Bottom,
+                _ => LatticeValue::Top,
+            }
+        }
+        _ => LatticeValue::Top,
+    }
+}
+
+/// Folds a pure binary operator over two known-constant operands.
+fn eval_binary_operator(
+    operator: BinaryOperator,
+    left: &Primitive,
+    right: &Primitive,
+) -> Option<Primitive> {
+    match operator {
+        BinaryOperator::StrictEquals => Some(Primitive::Boolean(left == right)),
+    }
+}
+
+/// Reads the known boolean/primitive value of an `IfTerminal`'s condition
+/// place, if the lattice has proven it constant.
+fn condition_const(place: &Place, lattice: &Lattice) -> Option<Primitive> {
+    match lattice.get(&place.identifier) {
+        LatticeValue::Const(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// After the fixpoint, rewrites uses of `Const` identifiers to literal
+/// `InstructionValue`s and collapses `IfTerminal`s with a `Const`
+/// condition into a `GotoTerminal` toward the branch that's actually
+/// taken.
+fn rewrite_constants(hir: &mut HIR, lattice: &Lattice) {
+    for block in hir.blocks.values_mut() {
+        for instruction in &mut block.instructions {
+            if let LatticeValue::Const(value) = lattice.get(&instruction.lvalue.identifier) {
+                if !matches!(instruction.value, InstructionValue::Primitive(_)) {
+                    instruction.value = InstructionValue::Primitive(value);
+                }
+            }
+        }
+
+        if let TerminalValue::IfTerminal(terminal) = &block.terminal.value {
+            if let Some(Primitive::Boolean(test)) = condition_const(&terminal.test, lattice) {
+                let target = if test {
+                    terminal.consequent
+                } else {
+                    terminal.alternate
+                };
+                block.terminal.value = TerminalValue::GotoTerminal(GotoTerminal {
+                    block: target,
+                    kind: GotoKind::Break,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meet_of_matching_constants_stays_const() {
+        let a = LatticeValue::Const(Primitive::Boolean(true));
+        let b = LatticeValue::Const(Primitive::Boolean(true));
+        assert_eq!(a.meet(&b), LatticeValue::Const(Primitive::Boolean(true)));
+    }
+
+    #[test]
+    fn meet_of_conflicting_constants_degrades_to_top() {
+        // Two incoming paths assigning the same identifier different
+        // constants must join to `Top`, not silently keep whichever path
+        // happened to be evaluated last.
+        let a = LatticeValue::Const(Primitive::Boolean(true));
+        let b = LatticeValue::Const(Primitive::Boolean(false));
+        assert_eq!(a.meet(&b), LatticeValue::Top);
+    }
+
+    #[test]
+    fn meet_of_bottom_and_const_is_const() {
+        let a = LatticeValue::Bottom;
+        let b = LatticeValue::Const(Primitive::Boolean(true));
+        assert_eq!(a.meet(&b), LatticeValue::Const(Primitive::Boolean(true)));
+    }
+
+    #[test]
+    fn eval_binary_operator_folds_strict_equals() {
+        let result = eval_binary_operator(
+            BinaryOperator::StrictEquals,
+            &Primitive::Boolean(true),
+            &Primitive::Boolean(true),
+        );
+        assert_eq!(result, Some(Primitive::Boolean(true)));
+    }
+}
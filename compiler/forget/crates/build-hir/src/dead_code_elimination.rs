@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+
+use hir::{BlockId, IdentifierId, InstructionValue, TerminalValue, HIR};
+
+use crate::builder::mark_instruction_ids;
+
+/// Whether an `InstructionValue` can be dropped if its result is unused.
+/// Calls, property stores, JSX, and anything that may throw must be kept
+/// even when dead, since removing them could change observable behavior.
+/// `BinaryExpression` is a plain value computation with no such effects,
+/// so it's eligible once dead just like a literal or a copy.
+fn is_pure(value: &InstructionValue) -> bool {
+    matches!(
+        value,
+        InstructionValue::Primitive(_)
+            | InstructionValue::LoadLocal(_)
+            | InstructionValue::BinaryExpression(_)
+    )
+}
+
+/// Backward-liveness dead store / dead code elimination, analogous to
+/// rustc's `dead_store_elimination`. Iterates to a fixpoint because
+/// removing one store can make another, earlier store dead (eg a temporary
+/// that only fed the now-dead store).
+pub fn dead_code_elimination(hir: &mut HIR) {
+    loop {
+        let live_in = compute_liveness(hir);
+        if !remove_dead_instructions(hir, &live_in) {
+            break;
+        }
+    }
+    let _ = mark_instruction_ids(hir);
+}
+
+/// Computes each block's live-in set with a backward dataflow fixpoint:
+/// live-out is the union of successors' live-in, and live-in is
+/// `(live_out \ defs) ∪ uses`, scanning the block's instructions and
+/// terminal from bottom to top.
+fn compute_liveness(hir: &HIR) -> HashMap<BlockId, HashSet<IdentifierId>> {
+    let mut live_in: HashMap<BlockId, HashSet<IdentifierId>> =
+        hir.blocks.keys().map(|id| (*id, HashSet::new())).collect();
+
+    // Iterate blocks in reverse of the builder's reverse-postorder (ie
+    // postorder), which converges fastest for backward dataflow problems.
+    let block_ids: std::vec::Vec<BlockId> = hir.blocks.keys().rev().cloned().collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block_id in &block_ids {
+            let block = hir.block(*block_id);
+            let mut live: HashSet<IdentifierId> = HashSet::new();
+            for successor in block.terminal.value.successors() {
+                live.extend(live_in.get(&successor).cloned().unwrap_or_default());
+            }
+            terminal_uses(&block.terminal.value, &mut live);
+            for instruction in block.instructions.iter().rev() {
+                live.remove(&instruction.lvalue.identifier.id);
+                instruction_uses(&instruction.value, &mut live);
+            }
+            let entry = live_in.get_mut(block_id).unwrap();
+            if *entry != live {
+                *entry = live;
+                changed = true;
+            }
+        }
+    }
+
+    live_in
+}
+
+/// Drops any instruction whose lvalue isn't live immediately after it,
+/// provided the instruction is pure. Returns whether anything was removed.
+fn remove_dead_instructions(
+    hir: &mut HIR,
+    live_in: &HashMap<BlockId, HashSet<IdentifierId>>,
+) -> bool {
+    let mut removed_any = false;
+    let block_ids: std::vec::Vec<BlockId> = hir.blocks.keys().cloned().collect();
+    for block_id in block_ids {
+        let block = hir.blocks.get_mut(&block_id).unwrap();
+        let mut live: HashSet<IdentifierId> = HashSet::new();
+        for successor in block.terminal.value.successors() {
+            live.extend(live_in.get(&successor).cloned().unwrap_or_default());
+        }
+        terminal_uses(&block.terminal.value, &mut live);
+
+        let mut dead: HashSet<usize> = HashSet::new();
+        for (ix, instruction) in block.instructions.iter().enumerate().rev() {
+            let is_live = live.contains(&instruction.lvalue.identifier.id);
+            if !is_live && is_pure(&instruction.value) {
+                dead.insert(ix);
+                continue;
+            }
+            live.remove(&instruction.lvalue.identifier.id);
+            instruction_uses(&instruction.value, &mut live);
+        }
+
+        if !dead.is_empty() {
+            removed_any = true;
+            let mut ix = 0;
+            block.instructions.retain(|_| {
+                let keep = !dead.contains(&ix);
+                ix += 1;
+                keep
+            });
+        }
+    }
+    removed_any
+}
+
+/// Collects the places read by an instruction's operands into `live`. Must
+/// cover every variant with operands -- a producer that only feeds a
+/// non-`LoadLocal` consumer (eg a `BinaryExpression`'s `left`/`right`)
+/// would otherwise look unused and be deleted out from under that
+/// consumer, corrupting the HIR rather than just missing an optimization.
+fn instruction_uses(value: &InstructionValue, live: &mut HashSet<IdentifierId>) {
+    match value {
+        InstructionValue::LoadLocal(load) => {
+            live.insert(load.place.identifier.id);
+        }
+        InstructionValue::BinaryExpression(binary) => {
+            live.insert(binary.left.identifier.id);
+            live.insert(binary.right.identifier.id);
+        }
+        InstructionValue::Primitive(_) => {}
+        _ => {}
+    }
+}
+
+/// Collects the places read by a terminal (eg an `IfTerminal`'s test).
+fn terminal_uses(value: &TerminalValue, live: &mut HashSet<IdentifierId>) {
+    match value {
+        TerminalValue::IfTerminal(terminal) => {
+            live.insert(terminal.test.identifier.id);
+        }
+        TerminalValue::SwitchTerminal(terminal) => {
+            live.insert(terminal.discriminant.identifier.id);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hir::Primitive;
+
+    #[test]
+    fn primitives_are_pure() {
+        assert!(is_pure(&InstructionValue::Primitive(Primitive::Boolean(
+            true
+        ))));
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use hir::{
+    BasicBlock, BinaryOperator, BlockId, IdentifierId, InstructionValue, Place, Primitive,
+    SwitchTerminal, TerminalValue, HIR,
+};
+
+use crate::builder::{mark_instruction_ids, mark_predecessors, reverse_postorder_blocks};
+
+/// Recognizes cascades of `IfTerminal`s that all test strict-equality of
+/// the *same* discriminant against distinct constants -- the shape
+/// produced by lowering `if (x === "a") ... else if (x === "b") ...` --
+/// and collapses them into a single `SwitchTerminal`. Inspired by rustc's
+/// `early_otherwise_branch`/`SwitchTargets::as_static_if`. Gives downstream
+/// analyses an explicit N-way dispatch instead of a deep if-chain.
+pub fn early_otherwise_branch(hir: &mut HIR) {
+    let block_ids: std::vec::Vec<BlockId> = hir.blocks.keys().cloned().collect();
+    for head_id in block_ids {
+        try_collapse_chain(hir, head_id);
+    }
+
+    reverse_postorder_blocks(hir);
+    let _ = mark_instruction_ids(hir);
+    mark_predecessors(hir);
+}
+
+/// If `head_id` starts a chain of `if (discriminant === const) ... else
+/// if (discriminant === const2) ...`, rewrites it into a `SwitchTerminal`
+/// and drops the now-dead intermediate test blocks. Each intermediate
+/// block (every block in the chain but `head_id`) must have no other
+/// predecessor and must contain nothing beyond the constant materialization
+/// and comparison, since it's about to be deleted.
+fn try_collapse_chain(hir: &mut HIR, head_id: BlockId) {
+    let mut cases: std::vec::Vec<(Primitive, BlockId)> = std::vec::Vec::new();
+    let mut intermediate_blocks: std::vec::Vec<BlockId> = std::vec::Vec::new();
+    let mut discriminant: Option<(IdentifierId, Place)> = None;
+    let mut current = head_id;
+    let default: BlockId;
+
+    loop {
+        let block = match hir.blocks.get(&current) {
+            Some(block) => block,
+            None => return,
+        };
+        let known_discriminant = discriminant.as_ref().map(|(id, _)| *id);
+        let test = match equality_test(block, known_discriminant) {
+            Some(test) => test,
+            None => return,
+        };
+
+        if discriminant.is_none() {
+            discriminant = Some((test.subject_id, test.subject_place));
+        }
+        cases.push((test.constant, test.consequent));
+        if current != head_id {
+            intermediate_blocks.push(current);
+        }
+
+        let continues_chain = hir
+            .blocks
+            .get(&test.alternate)
+            .map_or(false, |alternate_block| {
+                hir.block(test.alternate).predecessors.len() == 1
+                    && equality_test(alternate_block, discriminant.as_ref().map(|(id, _)| *id))
+                        .is_some()
+            });
+
+        if continues_chain {
+            current = test.alternate;
+        } else {
+            default = test.alternate;
+            break;
+        }
+    }
+
+    // A single comparison is already as simple as an `if`; only worth
+    // collapsing when there are at least two cases to dispatch on.
+    if cases.len() < 2 {
+        return;
+    }
+    let (_, discriminant_place) = discriminant.unwrap();
+
+    for block_id in &intermediate_blocks {
+        hir.blocks.remove(block_id);
+    }
+
+    let head = hir.blocks.get_mut(&head_id).unwrap();
+    head.terminal.value = TerminalValue::SwitchTerminal(SwitchTerminal {
+        discriminant: discriminant_place,
+        cases,
+        default,
+        fallthrough: None,
+    });
+}
+
+struct EqualityTest<'a> {
+    subject_id: IdentifierId,
+    subject_place: Place<'a>,
+    constant: Primitive,
+    consequent: BlockId,
+    alternate: BlockId,
+}
+
+/// Recognizes a block whose only job is comparing some identifier for
+/// strict equality against a materialized constant and branching on the
+/// result: `t0 = "a"; t1 = LoadLocal x; t2 = t1 === t0; if (t2) ... else
+/// ...`. `t1`'s comparison operand is resolved back through its `LoadLocal`
+/// to `x`'s own place before being checked against `expected_subject` --
+/// the per-site temporary `t1` is fresh at every link of an if-chain even
+/// when every link reads the same source binding. When `expected_subject`
+/// is given, only matches if the resolved subject is that identifier (used
+/// to confirm a chain keeps testing the same discriminant).
+fn equality_test<'a>(
+    block: &BasicBlock<'a>,
+    expected_subject: Option<IdentifierId>,
+) -> Option<EqualityTest<'a>> {
+    let (consequent, alternate, test_id) = match &block.terminal.value {
+        TerminalValue::IfTerminal(terminal) => (
+            terminal.consequent,
+            terminal.alternate,
+            terminal.test.identifier.id,
+        ),
+        _ => return None,
+    };
+
+    let mut constants: HashMap<IdentifierId, Primitive> = HashMap::new();
+    let mut loads: HashMap<IdentifierId, Place<'a>> = HashMap::new();
+    let mut comparison: Option<(Place<'a>, Place<'a>)> = None;
+    for instruction in block.instructions.iter() {
+        match &instruction.value {
+            InstructionValue::Primitive(primitive) => {
+                constants.insert(instruction.lvalue.identifier.id, primitive.clone());
+            }
+            InstructionValue::LoadLocal(load) => {
+                loads.insert(instruction.lvalue.identifier.id, load.place.clone());
+            }
+            InstructionValue::BinaryExpression(binary)
+                if instruction.lvalue.identifier.id == test_id
+                    && binary.operator == BinaryOperator::StrictEquals =>
+            {
+                comparison = Some((binary.left.clone(), binary.right.clone()));
+            }
+            // Anything else is a side-effecting (or otherwise unrecognized)
+            // instruction riding along with the comparison. The block isn't
+            // eligible for collapsing -- `try_collapse_chain` deletes
+            // intermediate blocks outright, which would silently drop this
+            // instruction along with it.
+            _ => return None,
+        }
+    }
+
+    // Resolves a comparison operand back to the place it was loaded from,
+    // if it's the result of a `LoadLocal` in this block; otherwise the
+    // operand already names the place directly.
+    let resolve = |place: &Place<'a>| -> Place<'a> {
+        loads.get(&place.identifier.id).cloned().unwrap_or_else(|| place.clone())
+    };
+
+    let (left, right) = comparison?;
+    let (subject, constant_place) = if constants.contains_key(&right.identifier.id)
+        && expected_subject.map_or(true, |id| id == resolve(&left).identifier.id)
+    {
+        (resolve(&left), right)
+    } else if constants.contains_key(&left.identifier.id)
+        && expected_subject.map_or(true, |id| id == resolve(&right).identifier.id)
+    {
+        (resolve(&right), left)
+    } else {
+        return None;
+    };
+
+    let constant = constants.get(&constant_place.identifier.id)?.clone();
+    Some(EqualityTest {
+        subject_id: subject.identifier.id,
+        subject_place: subject,
+        constant,
+        consequent,
+        alternate,
+    })
+}
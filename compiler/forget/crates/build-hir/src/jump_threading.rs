@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use bumpalo::collections::Vec as BumpVec;
+use hir::{
+    BasicBlock, BlockId, Environment, GotoKind, IdentifierId, Instruction, InstructionIdGenerator,
+    InstructionValue, Primitive, Terminal, TerminalValue, HIR,
+};
+
+use crate::builder::{mark_predecessors, reverse_postorder_blocks};
+
+/// Generalizes simple const-goto folding (see `constant_propagation`) into
+/// a jump-threading transform, following rustc's jump-threading design.
+/// For every block ending in an `IfTerminal`, walks backward through
+/// predecessors that are reached only via `GotoTerminal`s, looking for a
+/// point where the condition's value was fixed by a constant-boolean
+/// assignment. When found, the proven predecessor is redirected straight
+/// to the live branch, skipping the redundant test.
+pub fn jump_threading<'a>(hir: &mut HIR<'a>, environment: &'a Environment<'a>) {
+    // Block ids reachable only via a loop back-edge are never threaded
+    // through, since rewriting them could redirect control flow into the
+    // middle of a loop. `reverse_postorder_blocks` already orders blocks so
+    // that a predecessor with a *later* reverse-postorder index than its
+    // successor is a back-edge.
+    let order: HashMap<BlockId, usize> = hir
+        .blocks
+        .keys()
+        .enumerate()
+        .map(|(ix, id)| (*id, ix))
+        .collect();
+
+    let if_blocks: std::vec::Vec<BlockId> = hir
+        .blocks
+        .iter()
+        .filter(|(_, block)| matches!(block.terminal.value, TerminalValue::IfTerminal(_)))
+        .map(|(id, _)| *id)
+        .collect();
+
+    for if_block_id in if_blocks {
+        thread_block(hir, environment, &order, if_block_id);
+    }
+
+    reverse_postorder_blocks(hir);
+    mark_predecessors(hir);
+}
+
+/// Attempts to thread every predecessor of `if_block_id` that proves the
+/// condition constant.
+fn thread_block<'a>(
+    hir: &mut HIR<'a>,
+    environment: &'a Environment<'a>,
+    order: &HashMap<BlockId, usize>,
+    if_block_id: BlockId,
+) {
+    let (condition, consequent, alternate) = match &hir.block(if_block_id).terminal.value {
+        TerminalValue::IfTerminal(terminal) => (
+            terminal.test.identifier.id,
+            terminal.consequent,
+            terminal.alternate,
+        ),
+        _ => return,
+    };
+
+    // Threading bypasses the whole if-block, so only blocks holding nothing
+    // but the test qualify: any instruction there would be skipped (and
+    // might reassign the condition after a predecessor fixed it).
+    if !hir.block(if_block_id).instructions.is_empty() {
+        return;
+    }
+
+    let predecessors: std::vec::Vec<BlockId> =
+        hir.block(if_block_id).predecessors.iter().cloned().collect();
+
+    for predecessor_id in predecessors {
+        // Never thread across a loop back-edge: a predecessor with a
+        // reverse-postorder index greater than or equal to the if-block's
+        // own index indicates we've looped back around.
+        if order.get(&predecessor_id) >= order.get(&if_block_id) {
+            continue;
+        }
+        if let Some(target) = prove_condition(hir, order, if_block_id, predecessor_id, condition) {
+            let block_id = if target {
+                consequent
+            } else {
+                alternate
+            };
+            retarget_goto(hir, environment, predecessor_id, if_block_id, block_id);
+        }
+    }
+}
+
+/// Walks backward from `predecessor_id` through a chain of blocks whose
+/// only terminal is a `GotoTerminal`, looking for a point where
+/// `condition` was assigned a constant boolean. Stops as soon as a block
+/// isn't a plain goto, or it's visited twice (a cycle).
+fn prove_condition(
+    hir: &HIR,
+    order: &HashMap<BlockId, usize>,
+    if_block_id: BlockId,
+    predecessor_id: BlockId,
+    condition: IdentifierId,
+) -> Option<bool> {
+    let mut visited: HashSet<BlockId> = HashSet::new();
+    let mut current = predecessor_id;
+    let mut target = if_block_id;
+    loop {
+        if !visited.insert(current) {
+            return None;
+        }
+        let block = hir.block(current);
+        for instruction in block.instructions.iter().rev() {
+            if instruction.lvalue.identifier.id == condition {
+                return match &instruction.value {
+                    InstructionValue::Primitive(Primitive::Boolean(value)) => Some(*value),
+                    _ => None,
+                };
+            }
+        }
+        match &block.terminal.value {
+            TerminalValue::GotoTerminal(terminal) if terminal.block == target => {
+                if block.predecessors.len() != 1 {
+                    return None;
+                }
+                let next = *block.predecessors.iter().next().unwrap();
+                if order.get(&next) >= order.get(&current) {
+                    // Would cross a back-edge; stop here.
+                    return None;
+                }
+                target = current;
+                current = next;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Redirects `predecessor_id`'s `GotoTerminal` (which previously pointed
+/// at `if_block_id`) to jump straight to `target`. If `if_block_id` has
+/// other predecessors, the goto is pointed at a fresh copy of `target`
+/// (see `clone_block`) instead of `target` itself. Neither `if_block_id`
+/// nor the goto chain between it and the proving block is cloned; that
+/// chain is exclusive to the threaded path because `prove_condition` only
+/// walks through blocks with a single predecessor.
+fn retarget_goto<'a>(
+    hir: &mut HIR<'a>,
+    environment: &'a Environment<'a>,
+    predecessor_id: BlockId,
+    if_block_id: BlockId,
+    target: BlockId,
+) {
+    let needs_clone = hir.block(if_block_id).predecessors.len() > 1;
+    let goto_target = if needs_clone {
+        clone_block(hir, environment, target)
+    } else {
+        target
+    };
+
+    let block = hir.blocks.get_mut(&predecessor_id).unwrap();
+    if let TerminalValue::GotoTerminal(terminal) = &mut block.terminal.value {
+        terminal.block = goto_target;
+        terminal.kind = GotoKind::Break;
+    }
+}
+
+/// Shallow-clones a block under a fresh `BlockId` so a threaded edge can
+/// point at a private copy without disturbing the original block's other
+/// predecessors.
+fn clone_block<'a>(hir: &mut HIR<'a>, environment: &'a Environment<'a>, block_id: BlockId) -> BlockId {
+    let new_id = environment.next_block_id();
+    let mut id_gen = InstructionIdGenerator::new();
+    let source = hir.block(block_id);
+    let mut instructions = BumpVec::new_in(&environment.allocator);
+    for instruction in source.instructions.iter() {
+        instructions.push(Instruction {
+            id: id_gen.next(),
+            lvalue: instruction.lvalue.clone(),
+            value: instruction.value.clone(),
+        });
+    }
+    let terminal = Terminal {
+        id: id_gen.next(),
+        value: source.terminal.value.clone(),
+    };
+    hir.blocks.insert(
+        new_id,
+        BasicBlock {
+            id: new_id,
+            kind: source.kind,
+            instructions,
+            terminal,
+            predecessors: Default::default(),
+        },
+    );
+    new_id
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use hir::{BasicBlock, BlockId, BlockKind, TerminalValue, HIR};
+
+use crate::builder::{mark_instruction_ids, mark_predecessors, reverse_postorder_blocks};
+
+/// Shrinks the block count after `Builder::build`, combining ideas from
+/// rustc's block-merging and `deduplicate_blocks` passes: first merge
+/// linear goto chains, then fold structurally-identical blocks into one.
+pub fn simplify_cfg(hir: &mut HIR) {
+    mark_predecessors(hir);
+    merge_goto_chains(hir);
+    deduplicate_blocks(hir);
+
+    reverse_postorder_blocks(hir);
+    let _ = mark_instruction_ids(hir);
+    mark_predecessors(hir);
+}
+
+/// Repeatedly merges a block `P` ending in a `GotoTerminal` to `B` into a
+/// single block, provided `P` is `B`'s only predecessor and `B` isn't a
+/// loop header reached by a back-edge (detected via each block's position
+/// in the builder's reverse-postorder: a "predecessor" positioned at or
+/// after its target is a back-edge).
+fn merge_goto_chains(hir: &mut HIR) {
+    loop {
+        let order: HashMap<BlockId, usize> =
+            hir.blocks.keys().enumerate().map(|(ix, id)| (*id, ix)).collect();
+        let predecessor_ids: std::vec::Vec<BlockId> = hir.blocks.keys().cloned().collect();
+        let mut merged = false;
+
+        for p_id in predecessor_ids {
+            let target = match hir.blocks.get(&p_id) {
+                Some(block) => match &block.terminal.value {
+                    TerminalValue::GotoTerminal(terminal) => Some(terminal.block),
+                    _ => None,
+                },
+                None => None, // removed earlier this round
+            };
+            let Some(b_id) = target else { continue };
+            if b_id == p_id {
+                continue;
+            }
+            if order[&p_id] >= order[&b_id] {
+                // `b_id` comes no later than `p_id` in reverse-postorder: a back-edge.
+                continue;
+            }
+            let is_only_predecessor = hir
+                .blocks
+                .get(&b_id)
+                .map_or(false, |block| {
+                    block.predecessors.len() == 1 && block.predecessors.contains(&p_id)
+                });
+            if !is_only_predecessor {
+                continue;
+            }
+
+            let successor = hir.blocks.remove(&b_id).unwrap();
+            let predecessor = hir.blocks.get_mut(&p_id).unwrap();
+            predecessor.instructions.extend(successor.instructions);
+            predecessor.terminal = successor.terminal;
+            // `B`'s kind is semantically what survives the merge (eg a loop
+            // header whose only remaining predecessor is this goto) -- `P`
+            // was just a bare jump into it.
+            predecessor.kind = successor.kind;
+            merged = true;
+        }
+
+        if !merged {
+            break;
+        }
+        mark_predecessors(hir);
+    }
+}
+
+/// A comparable summary of a block's contents, ignoring `InstructionId`s
+/// and the block's own `BlockId` (but not the `BlockId`s a terminal
+/// targets, since those determine whether two blocks are truly
+/// interchangeable).
+type BlockSignature = (BlockKind, std::vec::Vec<String>, String);
+
+fn block_signature(block: &BasicBlock) -> BlockSignature {
+    let instructions = block
+        .instructions
+        .iter()
+        .map(|instruction| format!("{:?} = {:?}", instruction.lvalue, instruction.value))
+        .collect();
+    let terminal = format!("{:?}", block.terminal.value);
+    (block.kind, instructions, terminal)
+}
+
+/// Computes a structural signature per block and, for blocks that compare
+/// equal, redirects all edges pointing at the duplicates to a single
+/// canonical block before dropping the orphans.
+fn deduplicate_blocks(hir: &mut HIR) {
+    let mut canonical: HashMap<BlockSignature, BlockId> = HashMap::new();
+    let mut redirect: HashMap<BlockId, BlockId> = HashMap::new();
+    for (id, block) in hir.blocks.iter() {
+        let signature = block_signature(block);
+        match canonical.get(&signature) {
+            Some(existing) => {
+                redirect.insert(*id, *existing);
+            }
+            None => {
+                canonical.insert(signature, *id);
+            }
+        }
+    }
+    if redirect.is_empty() {
+        return;
+    }
+
+    for block in hir.blocks.values_mut() {
+        redirect_terminal(&mut block.terminal.value, &redirect);
+    }
+    for orphan in redirect.keys() {
+        hir.blocks.remove(orphan);
+    }
+}
+
+/// Rewrites every block-id-valued edge on a terminal (gotos, if-branches,
+/// for/do-while targets, and fallthroughs) per `redirect`.
+fn redirect_terminal(value: &mut TerminalValue, redirect: &HashMap<BlockId, BlockId>) {
+    match value {
+        TerminalValue::GotoTerminal(terminal) => {
+            if let Some(&to) = redirect.get(&terminal.block) {
+                terminal.block = to;
+            }
+        }
+        TerminalValue::IfTerminal(terminal) => {
+            if let Some(&to) = redirect.get(&terminal.consequent) {
+                terminal.consequent = to;
+            }
+            if let Some(&to) = redirect.get(&terminal.alternate) {
+                terminal.alternate = to;
+            }
+        }
+        TerminalValue::ForTerminal(terminal) => {
+            if let Some(&to) = redirect.get(&terminal.init) {
+                terminal.init = to;
+            }
+            if let Some(update) = terminal.update {
+                if let Some(&to) = redirect.get(&update) {
+                    terminal.update = Some(to);
+                }
+            }
+        }
+        TerminalValue::DoWhileTerminal(terminal) => {
+            if let Some(&to) = redirect.get(&terminal.body) {
+                terminal.body = to;
+            }
+            if let Some(&to) = redirect.get(&terminal.test) {
+                terminal.test = to;
+            }
+        }
+        TerminalValue::SwitchTerminal(terminal) => {
+            if let Some(&to) = redirect.get(&terminal.default) {
+                terminal.default = to;
+            }
+            for (_, case) in terminal.cases.iter_mut() {
+                if let Some(&to) = redirect.get(case) {
+                    *case = to;
+                }
+            }
+        }
+        TerminalValue::ReturnTerminal(..) => {}
+    }
+    value.map_optional_fallthroughs(|fallthrough| {
+        Some(*redirect.get(&fallthrough).unwrap_or(&fallthrough))
+    });
+}
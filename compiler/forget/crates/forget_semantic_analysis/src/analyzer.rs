@@ -17,6 +17,133 @@ pub fn analyze(ast: &Program) -> ScopeManager {
     analyzer.complete()
 }
 
+/// The JSX transform configuration for a module, resolved from `@jsx`,
+/// `@jsxFrag`, `@jsxRuntime`, and `@jsxImportSource` pragma comments.
+/// Mirrors the classic/automatic split that JSX transform pipelines use:
+/// the classic runtime lowers elements to calls against an explicit factory
+/// (`React.createElement`/`React.Fragment` by default), while the automatic
+/// runtime imports `jsx`/`jsxs` helpers from a runtime module and never
+/// references the factory by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsxRuntime {
+    Classic { pragma: String, pragma_frag: String },
+    Automatic { import_source: String },
+}
+
+impl Default for JsxRuntime {
+    fn default() -> Self {
+        JsxRuntime::Automatic {
+            import_source: "react".to_string(),
+        }
+    }
+}
+
+const DEFAULT_JSX_PRAGMA: &str = "React.createElement";
+const DEFAULT_JSX_PRAGMA_FRAG: &str = "React.Fragment";
+
+/// Scans every comment of the program (line or block, anywhere in the
+/// file, not only the leading ones) for JSX pragmas and resolves the
+/// runtime configuration they describe; when a pragma appears more than
+/// once, the last occurrence wins. `@jsx`/`@jsxFrag` imply the
+/// classic runtime even without an explicit `@jsxRuntime classic`; absent
+/// any pragmas, the module defaults to the automatic runtime importing
+/// from `"react"`.
+fn parse_jsx_pragma(ast: &Program) -> JsxRuntime {
+    let mut runtime: Option<&str> = None;
+    let mut pragma: Option<String> = None;
+    let mut pragma_frag: Option<String> = None;
+    let mut import_source: Option<String> = None;
+
+    for comment in &ast.comments {
+        for line in comment.value.lines() {
+            let line = line.trim().trim_start_matches('*').trim();
+            if let Some(value) = line.strip_prefix("@jsxRuntime ") {
+                runtime = Some(value.trim());
+            } else if let Some(value) = line.strip_prefix("@jsx ") {
+                pragma = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("@jsxFrag ") {
+                pragma_frag = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("@jsxImportSource ") {
+                import_source = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    if runtime == Some("classic") || pragma.is_some() || pragma_frag.is_some() {
+        JsxRuntime::Classic {
+            pragma: pragma.unwrap_or_else(|| DEFAULT_JSX_PRAGMA.to_string()),
+            pragma_frag: pragma_frag.unwrap_or_else(|| DEFAULT_JSX_PRAGMA_FRAG.to_string()),
+        }
+    } else {
+        JsxRuntime::Automatic {
+            import_source: import_source.unwrap_or_else(|| "react".to_string()),
+        }
+    }
+}
+
+/// Returns the leftmost identifier of a (possibly dotted) factory
+/// expression, eg `"React"` for `"React.createElement"`.
+fn jsx_pragma_root_name(pragma: &str) -> &str {
+    pragma.split('.').next().unwrap_or(pragma)
+}
+
+/// If a switch's discriminant is a simple identifier, returns its name so
+/// the switch's label can be described as `switch (x)` rather than just
+/// `switch`. More complex discriminants (member expressions, calls, ...)
+/// fall back to the generic label.
+fn switch_discriminant_debug_name(discriminant: &Expression) -> Option<String> {
+    match discriminant {
+        Expression::Identifier(ident) => Some(ident.name.clone()),
+        _ => None,
+    }
+}
+
+/// Renders a (possibly dotted/namespaced) JSX element name back to source
+/// text, eg `<Foo.Bar>`, for use in diagnostics and debug names.
+fn jsx_element_name_debug_name(name: &JSXElementName) -> String {
+    fn text(name: &JSXElementName) -> String {
+        match name {
+            JSXElementName::JSXIdentifier(ident) => ident.name.clone(),
+            JSXElementName::JSXMemberExpression(member) => {
+                format!("{}.{}", member_object_text(&member.object), member.property.name)
+            }
+            JSXElementName::JSXNamespacedName(ns) => {
+                format!("{}:{}", ns.namespace.name, ns.name.name)
+            }
+        }
+    }
+    fn member_object_text(object: &forget_estree::JSXMemberExpressionOrIdentifier) -> String {
+        match object {
+            forget_estree::JSXMemberExpressionOrIdentifier::JSXIdentifier(ident) => {
+                ident.name.clone()
+            }
+            forget_estree::JSXMemberExpressionOrIdentifier::JSXMemberExpression(member) => {
+                format!("{}.{}", member_object_text(&member.object), member.property.name)
+            }
+        }
+    }
+    format!("<{}>", text(name))
+}
+
+/// Joins the names declared by a `VariableDeclaration` (eg `"a, b"` for
+/// `let a, b = 1`), for use as a debug name. Destructuring patterns don't
+/// contribute a name since there's no single concise identifier for them.
+fn variable_declaration_debug_name(ast: &forget_estree::VariableDeclaration) -> String {
+    let names: std::vec::Vec<&str> = ast
+        .declarations
+        .iter()
+        .filter_map(|declaration| match &declaration.id {
+            Pattern::Identifier(ident) => Some(ident.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    if names.is_empty() {
+        "<destructured>".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
 struct Analyzer {
     manager: ScopeManager,
     labels: Vec<LabelId>,
@@ -24,6 +151,18 @@ struct Analyzer {
     unresolved: Vec<UnresolvedReference>,
 }
 
+/// A JSX element whose name is a host tag (`div`, `span`, ...) rather than a
+/// reference to a user-defined component binding. Host tags don't resolve
+/// to a declaration, but we still record them so downstream passes can
+/// enumerate every JSX element in a module, not just the ones that happen
+/// to reference a component.
+#[derive(Debug, Clone)]
+pub struct HostReference {
+    pub ast: AstNode,
+    pub name: String,
+    pub range: Option<SourceRange>,
+}
+
 #[derive(Debug, Clone)]
 pub struct UnresolvedReference {
     pub scope: ScopeId,
@@ -40,14 +179,16 @@ pub struct UnresolvedReference {
 
 impl Analyzer {
     fn new(program: &Program) -> Self {
-        let manager = ScopeManager::new(program.source_type);
+        let hint = Counter::count(program);
+        let mut manager = ScopeManager::with_capacity(program.source_type, &hint);
+        manager.jsx_runtime = parse_jsx_pragma(program);
         let current = manager.root_id();
         let labels = Default::default();
         Self {
             manager,
             labels,
             current,
-            unresolved: Default::default(),
+            unresolved: std::vec::Vec::with_capacity(hint.references),
         }
     }
 
@@ -64,7 +205,7 @@ impl Analyzer {
                 self.manager.node_references.insert(reference.ast, id);
             } else {
                 self.manager.diagnostics.push(Diagnostic::invalid_syntax(
-                    "Undefined variable",
+                    &format!("Undefined variable `{}`", reference.name),
                     reference.range,
                 ));
             }
@@ -212,7 +353,7 @@ impl Analyzer {
                 if previous_declaration.scope == self.current {
                     // duplicate definition in the same scope
                     self.manager.diagnostics.push(Diagnostic::invalid_syntax(
-                        "Duplicate declaration",
+                        &format!("Duplicate declaration `{}`", ast.name),
                         ast.range,
                     ));
                 }
@@ -273,6 +414,25 @@ impl Analyzer {
         }
     }
 
+    /// Under the classic JSX runtime, records a synthetic `Read` reference
+    /// to the root binding of the configured factory (`React` for the
+    /// default `React.createElement`/`React.Fragment` pragmas) so that
+    /// scope analysis tracks the implicit use. Under the automatic runtime
+    /// there's no factory reference to record; the import source was
+    /// already stashed on `self.manager.jsx_runtime` in `Analyzer::new`.
+    fn record_jsx_runtime_reference(&mut self, ast: AstNode, range: Option<SourceRange>, is_fragment: bool) {
+        let jsx_runtime = self.manager.jsx_runtime.clone();
+        if let JsxRuntime::Classic {
+            pragma,
+            pragma_frag,
+        } = jsx_runtime
+        {
+            let factory = if is_fragment { &pragma_frag } else { &pragma };
+            let name = jsx_pragma_root_name(factory).to_string();
+            Analyzer::visit_reference_identifier(self, &name, ast, ReferenceKind::Read, range);
+        }
+    }
+
     fn visit_for_in_of(
         &mut self,
         ast: AstNode,
@@ -280,6 +440,7 @@ impl Analyzer {
         right: &Expression,
         body: &Statement,
         _range: Option<SourceRange>,
+        debug_name: &str,
     ) {
         let mut for_scope: Option<ScopeId> = None;
         match left {
@@ -294,9 +455,9 @@ impl Analyzer {
             }
         }
         self.visit_expression(right);
-        let id = self
-            .manager
-            .add_anonymous_label(self.current, LabelKind::Loop);
+        let id =
+            self.manager
+                .add_anonymous_label(self.current, LabelKind::Loop, debug_name.to_string());
         self.manager.node_labels.insert(ast, id);
         self.enter_label(id, |visitor| {
             visitor.visit_statement(body);
@@ -559,6 +720,7 @@ impl Visitor for Analyzer {
             &ast.right,
             &ast.body,
             ast.range,
+            "for-in loop",
         );
     }
 
@@ -570,6 +732,7 @@ impl Visitor for Analyzer {
             &ast.right,
             &ast.body,
             ast.range,
+            "for-of loop",
         );
     }
 
@@ -591,9 +754,9 @@ impl Visitor for Analyzer {
         if let Some(update) = &ast.update {
             self.visit_expression(update);
         }
-        let id = self
-            .manager
-            .add_anonymous_label(self.current, LabelKind::Loop);
+        let id =
+            self.manager
+                .add_anonymous_label(self.current, LabelKind::Loop, "for loop".to_string());
         self.manager.node_labels.insert(AstNode::from(ast), id);
         self.enter_label(id, |visitor| {
             visitor.visit_statement(&ast.body);
@@ -678,9 +841,13 @@ impl Visitor for Analyzer {
 
     fn visit_switch_statement(&mut self, ast: &forget_estree::SwitchStatement) {
         self.visit_expression(&ast.discriminant);
-        let id = self
-            .manager
-            .add_anonymous_label(self.current, LabelKind::Other);
+        let debug_name = match switch_discriminant_debug_name(&ast.discriminant) {
+            Some(name) => format!("switch ({name})"),
+            None => "switch".to_string(),
+        };
+        let id =
+            self.manager
+                .add_anonymous_label(self.current, LabelKind::Other, debug_name);
         self.manager.node_labels.insert(AstNode::from(ast), id);
         self.enter_label(id, |visitor| {
             visitor.enter(ScopeKind::Switch, |visitor| {
@@ -692,6 +859,9 @@ impl Visitor for Analyzer {
     }
 
     fn visit_variable_declaration(&mut self, ast: &forget_estree::VariableDeclaration) {
+        self.manager
+            .node_debug_names
+            .insert(AstNode::from(ast), variable_declaration_debug_name(ast));
         let kind = ast.kind;
         for declaration in &ast.declarations {
             Analyzer::visit_declaration_pattern(self, &declaration.id, Some(kind.into()));
@@ -723,8 +893,28 @@ impl Visitor for Analyzer {
         );
     }
 
+    /// Resolves a JSX element's root name to the binding it refers to, the
+    /// same way a normal `Identifier` reference is resolved, so `<Foo />`
+    /// is linked to `Foo`'s declaration for use-counting. Host element
+    /// names (lowercase tags) never bind to a declaration, so they're
+    /// recorded as `HostReference`s instead of being pushed through the
+    /// declaration-resolution pipeline, which would otherwise report them
+    /// as undefined variables.
+    fn visit_jsx_element_name(&mut self, ast: &forget_estree::JSXIdentifier) {
+        let is_host = ast.name.chars().next().map_or(false, |first| first.is_lowercase());
+        if is_host {
+            self.manager.host_references.push(HostReference {
+                ast: AstNode::from(ast),
+                name: ast.name.clone(),
+                range: ast.range,
+            });
+        } else {
+            self.visit_jsxidentifier(ast);
+        }
+    }
+
     fn visit_jsxfragment(&mut self, ast: &forget_estree::JSXFragment) {
-        // TODO: record the pragmas
+        self.record_jsx_runtime_reference(AstNode::from(ast), ast.range, true);
         for child in &ast.children {
             self.visit_jsxchild_item(child);
         }
@@ -741,23 +931,23 @@ impl Visitor for Analyzer {
     }
 
     fn visit_jsxopening_element(&mut self, ast: &forget_estree::JSXOpeningElement) {
-        // TODO: record jsx pragma if root_name is not an FBT name
+        self.record_jsx_runtime_reference(AstNode::from(ast), ast.range, false);
+        let debug_name = jsx_element_name_debug_name(&ast.name);
+        self.manager
+            .node_debug_names
+            .insert(AstNode::from(ast), debug_name.clone());
         let root_name = ast.name.root_name();
 
         match &ast.name {
             JSXElementName::JSXIdentifier(name) => {
-                // lowercase names are builtins, only visit if this is a user-defined
-                // component
-                if let Some(first) = root_name.chars().next() {
-                    if first == first.to_ascii_uppercase() {
-                        self.visit_jsxidentifier(name);
-                    }
+                if root_name.chars().next().is_some() {
+                    self.visit_jsx_element_name(name);
                 } else {
                     // TODO: this likely indicates a parse error, since a valid parse
                     // should never result in an empty JSXIdentifier node. but just in
                     // case we report this rather than silently fail
                     self.manager.diagnostics.push(Diagnostic::invalid_syntax(
-                        "Expected JSXOpeningElement.name to be non-empty",
+                        &format!("Expected JSXOpeningElement.name to be non-empty (in {})", debug_name),
                         name.range,
                     ));
                 }
@@ -779,3 +969,293 @@ impl Visitor for Analyzer {
         }
     }
 }
+
+/// Estimated totals produced by a cheap counting pre-pass over the AST,
+/// used to `with_capacity` the Analyzer's scope/label/reference tables
+/// before the real walk begins, so they don't incrementally reallocate on
+/// large modules. The totals are capacity estimates, not exact counts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnalysisSizeHint {
+    /// Number of AST nodes counted by the pre-pass; only the node types
+    /// that `Counter` overrides are counted, other nodes are walked but
+    /// not counted.
+    pub nodes: usize,
+    /// Estimated number of scopes (function/block/switch/for/catch).
+    pub scopes: usize,
+    /// Estimated number of binding patterns (declared symbols).
+    pub symbols: usize,
+    /// Estimated number of identifier and JSX identifier references.
+    pub references: usize,
+}
+
+/// A minimal visitor that only counts AST nodes, scopes, symbols, and
+/// references; it mirrors the node-entering, scope-entering,
+/// binding-pattern, identifier-reference, and JSX-identifier hooks that
+/// `Analyzer` uses. Its totals can differ from what the real walk creates:
+/// assignment targets that are patterns are counted as symbols although
+/// the real walk records them as references, and host tag names (`<div>`)
+/// are counted as references although the real walk records them as
+/// `HostReference`s. This is a pure performance change: it has no effect
+/// on the `ScopeManager` that's eventually produced.
+#[derive(Default)]
+struct Counter {
+    hint: AnalysisSizeHint,
+    is_classic_jsx_runtime: bool,
+}
+
+impl Counter {
+    fn count(ast: &Program) -> AnalysisSizeHint {
+        let mut counter = Self {
+            is_classic_jsx_runtime: matches!(parse_jsx_pragma(ast), JsxRuntime::Classic { .. }),
+            ..Self::default()
+        };
+        counter.visit_program(ast);
+        counter.hint
+    }
+
+    fn visit_declaration_pattern(&mut self, ast: &Pattern) {
+        self.hint.nodes += 1;
+        match ast {
+            Pattern::Identifier(_) => {
+                self.hint.symbols += 1;
+            }
+            Pattern::ArrayPattern(ast) => {
+                for pat in &ast.elements {
+                    if let Some(pat) = pat {
+                        self.visit_declaration_pattern(pat);
+                    }
+                }
+            }
+            Pattern::ObjectPattern(ast) => {
+                for property in &ast.properties {
+                    match property {
+                        AssignmentPropertyOrRestElement::AssignmentProperty(property) => {
+                            if property.is_computed {
+                                self.visit_expression(&property.key);
+                            }
+                            self.visit_declaration_pattern(&property.value);
+                        }
+                        AssignmentPropertyOrRestElement::RestElement(property) => {
+                            self.visit_declaration_pattern(&property.argument);
+                        }
+                    }
+                }
+            }
+            Pattern::RestElement(ast) => {
+                self.visit_declaration_pattern(&ast.argument);
+            }
+            Pattern::AssignmentPattern(ast) => {
+                self.visit_expression(&ast.right);
+                self.visit_declaration_pattern(&ast.left);
+            }
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.hint.nodes += 1;
+        self.hint.scopes += 1;
+    }
+}
+
+impl Visitor for Counter {
+    fn visit_function_declaration(&mut self, ast: &forget_estree::FunctionDeclaration) {
+        self.hint.nodes += 1;
+        if ast.function.id.is_some() {
+            self.hint.symbols += 1;
+        }
+        self.enter_scope();
+        for param in &ast.function.params {
+            self.visit_declaration_pattern(param);
+        }
+        if let Some(body) = &ast.function.body {
+            match body {
+                FunctionBody::BlockStatement(body) => {
+                    for item in &body.body {
+                        self.visit_statement(item);
+                    }
+                }
+                FunctionBody::Expression(body) => {
+                    self.visit_expression(body);
+                }
+            }
+        }
+    }
+
+    fn visit_function_expression(&mut self, ast: &forget_estree::FunctionExpression) {
+        self.hint.nodes += 1;
+        if ast.function.id.is_some() {
+            self.hint.symbols += 1;
+            self.enter_scope();
+        }
+        self.enter_scope();
+        for param in &ast.function.params {
+            self.visit_declaration_pattern(param);
+        }
+        if let Some(body) = &ast.function.body {
+            match body {
+                FunctionBody::BlockStatement(body) => {
+                    for item in &body.body {
+                        self.visit_statement(item);
+                    }
+                }
+                FunctionBody::Expression(body) => {
+                    self.visit_expression(body);
+                }
+            }
+        }
+    }
+
+    fn visit_arrow_function_expression(&mut self, ast: &forget_estree::ArrowFunctionExpression) {
+        self.hint.nodes += 1;
+        self.enter_scope();
+        for param in &ast.function.params {
+            self.visit_declaration_pattern(param);
+        }
+        if let Some(body) = &ast.function.body {
+            match body {
+                FunctionBody::BlockStatement(body) => {
+                    for item in &body.body {
+                        self.visit_statement(item);
+                    }
+                }
+                FunctionBody::Expression(body) => {
+                    self.visit_expression(body);
+                }
+            }
+        }
+    }
+
+    fn visit_block_statement(&mut self, ast: &forget_estree::BlockStatement) {
+        self.enter_scope();
+        for stmt in &ast.body {
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn visit_catch_clause(&mut self, ast: &forget_estree::CatchClause) {
+        if let Some(param) = &ast.param {
+            self.enter_scope();
+            self.visit_declaration_pattern(param);
+        }
+        self.visit_block_statement(&ast.body);
+    }
+
+    fn visit_switch_statement(&mut self, ast: &forget_estree::SwitchStatement) {
+        self.hint.nodes += 1;
+        self.visit_expression(&ast.discriminant);
+        self.enter_scope();
+        for case_ in &ast.cases {
+            self.visit_switch_case(case_);
+        }
+    }
+
+    fn visit_for_statement(&mut self, ast: &forget_estree::ForStatement) {
+        self.hint.nodes += 1;
+        if let Some(init) = &ast.init {
+            if let ForInit::VariableDeclaration(init) = init {
+                if init.kind != VariableDeclarationKind::Var {
+                    self.enter_scope();
+                }
+            }
+            self.visit_for_init(init);
+        }
+        if let Some(test) = &ast.test {
+            self.visit_expression(test);
+        }
+        if let Some(update) = &ast.update {
+            self.visit_expression(update);
+        }
+        self.visit_statement(&ast.body);
+    }
+
+    fn visit_for_in_statement(&mut self, ast: &forget_estree::ForInStatement) {
+        self.hint.nodes += 1;
+        self.visit_for_in_of(&ast.left, &ast.right, &ast.body);
+    }
+
+    fn visit_for_of_statement(&mut self, ast: &forget_estree::ForOfStatement) {
+        self.hint.nodes += 1;
+        self.visit_for_in_of(&ast.left, &ast.right, &ast.body);
+    }
+
+    fn visit_variable_declaration(&mut self, ast: &forget_estree::VariableDeclaration) {
+        self.hint.nodes += 1;
+        for declaration in &ast.declarations {
+            self.visit_declaration_pattern(&declaration.id);
+            if let Some(init) = &declaration.init {
+                self.visit_expression(init);
+            }
+        }
+    }
+
+    fn visit_assignment_expression(&mut self, ast: &forget_estree::AssignmentExpression) {
+        self.hint.nodes += 1;
+        if let AssignmentTarget::Pattern(left) = &ast.left {
+            self.visit_declaration_pattern(left);
+        } else {
+            self.hint.references += 1;
+        }
+        self.visit_expression(&ast.right);
+    }
+
+    fn visit_identifier(&mut self, _ast: &forget_estree::Identifier) {
+        self.hint.nodes += 1;
+        self.hint.references += 1;
+    }
+
+    fn visit_jsxidentifier(&mut self, _ast: &forget_estree::JSXIdentifier) {
+        self.hint.nodes += 1;
+        self.hint.references += 1;
+    }
+
+    fn visit_jsxmember_expression(&mut self, ast: &forget_estree::JSXMemberExpression) {
+        self.hint.nodes += 1;
+        self.visit_jsxmember_expression_or_identifier(&ast.object);
+    }
+
+    fn visit_jsxopening_element(&mut self, ast: &forget_estree::JSXOpeningElement) {
+        self.hint.nodes += 1;
+        // Mirrors `Analyzer::record_jsx_runtime_reference`: under the
+        // classic runtime, every JSX element records a synthetic reference
+        // to the pragma's root binding (eg `React`).
+        if self.is_classic_jsx_runtime {
+            self.hint.references += 1;
+        }
+        match &ast.name {
+            JSXElementName::JSXIdentifier(name) => self.visit_jsxidentifier(name),
+            JSXElementName::JSXMemberExpression(name) => self.visit_jsxmember_expression(name),
+            JSXElementName::JSXNamespacedName(name) => self.visit_jsxidentifier(&name.namespace),
+        }
+        for attribute in &ast.attributes {
+            self.visit_jsxattribute_or_spread(attribute);
+        }
+    }
+
+    fn visit_jsxfragment(&mut self, ast: &forget_estree::JSXFragment) {
+        self.hint.nodes += 1;
+        if self.is_classic_jsx_runtime {
+            self.hint.references += 1;
+        }
+        for child in &ast.children {
+            self.visit_jsxchild_item(child);
+        }
+    }
+}
+
+impl Counter {
+    fn visit_for_in_of(&mut self, left: &ForInInit, right: &Expression, body: &Statement) {
+        match left {
+            ForInInit::VariableDeclaration(left) => {
+                if left.kind != VariableDeclarationKind::Var {
+                    self.enter_scope();
+                }
+                self.visit_variable_declaration(left);
+            }
+            ForInInit::Pattern(left) => {
+                self.visit_declaration_pattern(left);
+            }
+        }
+        self.visit_expression(right);
+        self.visit_statement(body);
+    }
+}